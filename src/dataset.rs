@@ -0,0 +1,70 @@
+// A lightweight view over a dataset: a borrowed slice of rows plus a set of
+// row indices into it. Trees are built against this view so that subsampling
+// and splitting only shuffle indices instead of cloning whole feature vectors.
+pub struct DatasetRange<'a> {
+    data: &'a [Vec<f64>],
+    indices: &'a mut [usize],
+}
+
+impl<'a> DatasetRange<'a> {
+    pub fn new(data: &'a [Vec<f64>], indices: &'a mut [usize]) -> Self {
+        DatasetRange { data, indices }
+    }
+
+    // Number of rows in this range.
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    // Number of features (columns); 0 for an empty dataset.
+    pub fn num_features(&self) -> usize {
+        if self.data.is_empty() {
+            0
+        } else {
+            self.data[self.indices[0]].len()
+        }
+    }
+
+    // Resolve the value at `row` (within this range) and `col` through the
+    // index vector.
+    pub fn at(&self, row: usize, col: usize) -> f64 {
+        self.data[self.indices[row]][col]
+    }
+
+    // Partition the index slice in place so that every row whose `mask` entry
+    // is true comes first, and return the number of such rows. The two halves
+    // can then be handed to child ranges via `split_at`. `mask` is indexed by
+    // row position and must have the same length as this range.
+    pub fn partition_mask(&mut self, mask: &[bool]) -> usize {
+        let mut flags = mask.to_vec();
+        let mut boundary = 0;
+        for row in 0..self.indices.len() {
+            if flags[row] {
+                self.indices.swap(boundary, row);
+                flags.swap(boundary, row);
+                boundary += 1;
+            }
+        }
+        boundary
+    }
+
+    // Split this range into a left range (first `mid` rows) and a right range
+    // (the remainder), each borrowing a disjoint slice of the same indices.
+    pub fn split_at(self, mid: usize) -> (DatasetRange<'a>, DatasetRange<'a>) {
+        let (left, right) = self.indices.split_at_mut(mid);
+        (
+            DatasetRange {
+                data: self.data,
+                indices: left,
+            },
+            DatasetRange {
+                data: self.data,
+                indices: right,
+            },
+        )
+    }
+}
@@ -2,18 +2,76 @@ use rand::rngs::StdRng;
 use rand::{SeedableRng, Rng};
 use std::cmp::min;
 
+use crate::dataset::DatasetRange;
 use crate::node::IsolationTreeNode;
 
+// Serializable view of a fitted forest. The `StdRng` carries no useful state
+// for scoring, so it is left out here and re-seeded when the model is loaded.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ForestView {
+    trees: Vec<IsolationTreeNode>,
+    num_trees: usize,
+    subsample_size: usize,
+    max_tree_height: usize,
+    extension_level: usize,
+}
+
+// Configuration for building an `IsolationForest`. Construct it directly or
+// via the builder methods, then hand it to `IsolationForest::from_options`.
+pub struct ForestOptions {
+    pub n_trees: usize,
+    pub sample_size: usize,
+    // Explicit tree height limit; defaults to ceil(log2(sample_size)) when None.
+    pub max_tree_depth: Option<usize>,
+    pub seed: Option<u64>,
+    pub extension_level: usize,
+}
+
+impl ForestOptions {
+    // Take the two required parameters and default the optional ones.
+    pub fn new(n_trees: usize, sample_size: usize) -> Self {
+        ForestOptions {
+            n_trees,
+            sample_size,
+            max_tree_depth: None,
+            seed: None,
+            extension_level: 0,
+        }
+    }
+
+    pub fn max_tree_depth(mut self, max_tree_depth: usize) -> Self {
+        self.max_tree_depth = Some(max_tree_depth);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn extension_level(mut self, extension_level: usize) -> Self {
+        self.extension_level = extension_level;
+        self
+    }
+}
+
 pub struct IsolationForest {
     trees: Vec<IsolationTreeNode>,
     num_trees: usize,
     subsample_size: usize,
     max_tree_height: usize,
+    extension_level: usize,
     rng: StdRng,
 }
 
 impl IsolationForest {
-    pub fn new(num_trees: usize, subsample_size: usize, seed: Option<u64>) -> Self {
+    pub fn new(
+        num_trees: usize,
+        subsample_size: usize,
+        extension_level: usize,
+        seed: Option<u64>,
+    ) -> Self {
         let rng = match seed {
             Some(s) => StdRng::seed_from_u64(s),
             None => StdRng::from_os_rng(),
@@ -26,21 +84,53 @@ impl IsolationForest {
             num_trees,
             subsample_size,
             max_tree_height,
+            extension_level,
             rng,
         }
     }
 
+    // Build a forest from a `ForestOptions`, validating the parameters. When
+    // `max_tree_depth` is `None` the height limit defaults to
+    // ceil(log2(sample_size)); otherwise the explicit depth is used.
+    pub fn from_options(options: ForestOptions) -> Result<Self, String> {
+        if options.sample_size == 0 {
+            return Err("sample_size must be at least 1".to_string());
+        }
+
+        let rng = match options.seed {
+            Some(s) => StdRng::seed_from_u64(s),
+            None => StdRng::from_os_rng(),
+        };
+
+        let max_tree_height = match options.max_tree_depth {
+            Some(d) => d,
+            None => (options.sample_size as f64).log2().ceil() as usize,
+        };
+
+        Ok(IsolationForest {
+            trees: Vec::with_capacity(options.n_trees),
+            num_trees: options.n_trees,
+            subsample_size: options.sample_size,
+            max_tree_height,
+            extension_level: options.extension_level,
+            rng,
+        })
+    }
+
     pub fn fit(&mut self, data: &[Vec<f64>]) {
         self.trees.clear();
 
         for _ in 0..self.num_trees {
-            let subsample = self.get_random_subsample(data);
+            // Sample row indices and build a tree over a lightweight view of
+            // the data, so no rows are cloned into the tree.
+            let mut indices = self.get_random_subsample(data);
+            let range = DatasetRange::new(data, &mut indices);
 
-            // Build a tree with the subsample
             let tree = IsolationTreeNode::build_isolation_tree(
-                &subsample,
+                range,
                 0,
                 self.max_tree_height,
+                self.extension_level,
                 &mut self.rng,
             );
 
@@ -49,27 +139,27 @@ impl IsolationForest {
         }
     }
 
-    // Take a random subsample of the data
-    fn get_random_subsample(&mut self, data: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    // Take a random subsample of the data, returned as row indices.
+    fn get_random_subsample(&mut self, data: &[Vec<f64>]) -> Vec<usize> {
         let data_size = data.len();
         let sample_size = min(self.subsample_size, data_size);
 
-        // If sample size == data size just clone the whole dataset
+        // If sample size == data size use every row as-is.
         if sample_size == data_size {
-            return data.to_vec();
+            return (0..data_size).collect();
         }
 
-        // Create a set of randomly selected indices
-        let mut indices = Vec::with_capacity(sample_size);
-        while indices.len() < sample_size {
-            let idx = self.rng.random_range(0..data_size);
-            if !indices.contains(&idx) {
-                indices.push(idx);
-            }
+        // Partial Fisher-Yates shuffle: draw `sample_size` distinct indices
+        // without replacement in O(data_size), avoiding the unbounded retry
+        // loop that degrades as `subsample_size` approaches `data.len()`.
+        let mut indices: Vec<usize> = (0..data_size).collect();
+        for i in 0..sample_size {
+            let j = self.rng.random_range(i..data_size);
+            indices.swap(i, j);
         }
+        indices.truncate(sample_size);
 
-        // Create subsample from selected indices
-        indices.iter().map(|&idx| data[idx].clone()).collect()
+        indices
     }
 
     // Scoring function - return scoring for each data point
@@ -91,6 +181,40 @@ impl IsolationForest {
         2.0f64.powf(-avg_path_len / norm_factor)
     }
 
+    // Explain why an instance got its score by attributing it across features.
+    // For every tree, the instance's decision path is traced; each split adds
+    // 1 / (depth + 1) to its feature's weight, with the root at depth 0 (so
+    // shallow, early splits count more). Weights are summed over the trees
+    // and normalized to sum to 1, yielding a per-feature contribution vector.
+    pub fn explain(&self, instance: &[f64]) -> Vec<f64> {
+        let num_features = instance.len();
+        let mut contributions = vec![0.0; num_features];
+
+        if self.trees.is_empty() {
+            return contributions;
+        }
+
+        let mut trace = Vec::new();
+        for tree in self.trees.iter() {
+            trace.clear();
+            tree.path_trace(instance, &mut trace);
+
+            for (step, &(feature, _split_value, _went_left)) in trace.iter().enumerate() {
+                // Shallow splits (small step index) contribute more.
+                contributions[feature] += 1.0 / (step as f64 + 1.0);
+            }
+        }
+
+        let total: f64 = contributions.iter().sum();
+        if total > 0.0 {
+            for contribution in contributions.iter_mut() {
+                *contribution /= total;
+            }
+        }
+
+        contributions
+    }
+
     // Calculate average path length for an instance across all trees
     fn avg_path_len(&self, instance: &[f64]) -> f64 {
         if self.trees.is_empty() {
@@ -106,4 +230,37 @@ impl IsolationForest {
 
         sum_path_len / self.trees.len() as f64
     }
+
+    // Serialize the fitted forest as JSON into `writer`. The RNG is not
+    // persisted; a freshly seeded one is created on load.
+    #[cfg(feature = "serde")]
+    pub fn save_to_writer<W: std::io::Write>(
+        &self,
+        writer: W,
+    ) -> Result<(), serde_json::Error> {
+        let view = ForestView {
+            trees: self.trees.clone(),
+            num_trees: self.num_trees,
+            subsample_size: self.subsample_size,
+            max_tree_height: self.max_tree_height,
+            extension_level: self.extension_level,
+        };
+        serde_json::to_writer(writer, &view)
+    }
+
+    // Load a previously saved forest from `reader`. The RNG is re-seeded from
+    // the OS, since it is only used during `fit`.
+    #[cfg(feature = "serde")]
+    pub fn load_from_reader<R: std::io::Read>(reader: R) -> Result<Self, serde_json::Error> {
+        let view: ForestView = serde_json::from_reader(reader)?;
+
+        Ok(IsolationForest {
+            trees: view.trees,
+            num_trees: view.num_trees,
+            subsample_size: view.subsample_size,
+            max_tree_height: view.max_tree_height,
+            extension_level: view.extension_level,
+            rng: StdRng::from_os_rng(),
+        })
+    }
 }
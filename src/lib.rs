@@ -0,0 +1,3 @@
+pub mod dataset;
+pub mod forest;
+pub mod node;
@@ -1,7 +1,4 @@
-mod forest;
-mod node;
-
-use forest::IsolationForest;
+use iforest::forest::IsolationForest;
 
 fn main() {
     let data = vec![
@@ -13,8 +10,9 @@ fn main() {
         vec![10.0, 20.0], // outlier!
     ];
 
-    // Create a forest with 100 trees, subsample size of 4, and a random seed
-    let mut forest = IsolationForest::new(100, 4, Some(42));
+    // Create a forest with 100 trees, subsample size of 4, axis-parallel
+    // splits (extension_level = 0), and a fixed seed
+    let mut forest = IsolationForest::new(100, 4, 0, Some(42));
 
     // Fit the forest
     forest.fit(&data);
@@ -29,4 +27,12 @@ fn main() {
     }
 
     println!("\nHigher scores (closer to 1.0) indicate anomalies");
+
+    // Explain which features drove the most anomalous point's score.
+    let outlier = &data[data.len() - 1];
+    let contributions = forest.explain(outlier);
+    println!("\nPer-feature contributions for point {:?}:", outlier);
+    for (feature, weight) in contributions.iter().enumerate() {
+        println!("Feature {}: {:.6}", feature, weight);
+    }
 }
@@ -1,11 +1,22 @@
+use std::cmp::min;
+use std::collections::HashSet;
+
 use rand::Rng;
 
-#[derive(Debug)]
+use crate::dataset::DatasetRange;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum IsolationTreeNode {
-    // Internal node that splits data
+    // Internal node that splits data on a random hyperplane. With
+    // `extension_level = 0` the normal has a single non-zero component, which
+    // reduces to the classic axis-parallel split.
     Internal {
-        split_feature: usize,
-        split_value: f64,
+        // Normal vector of the splitting hyperplane (mostly zeros, with
+        // `extension_level + 1` non-zero components)
+        normal: Vec<f64>,
+        // Intercept point drawn from the per-feature min/max box
+        intercept: Vec<f64>,
         left: Box<IsolationTreeNode>,
         right: Box<IsolationTreeNode>,
         #[allow(dead_code)]
@@ -28,16 +39,16 @@ impl IsolationTreeNode {
                 // Return the current path length + correction factor?
                 curr_len as f64 + Self::c(*size)
             }
-            // If @internal node
+            // If @internal node, traverse by the hyperplane test
             IsolationTreeNode::Internal {
-                split_feature,
-                split_value,
+                normal,
+                intercept,
                 left,
                 right,
                 ..
             } => {
-                // Decide which child to traverse based on split
-                if instance[*split_feature] < *split_value {
+                // An instance goes left iff dot(instance - p, n) <= 0
+                if Self::hyperplane_dot(instance, normal, intercept) <= 0.0 {
                     left.path_len(instance, curr_len + 1)
                 } else {
                     right.path_len(instance, curr_len + 1)
@@ -46,6 +57,55 @@ impl IsolationTreeNode {
         }
     }
 
+    // Record the decision path taken by `instance`, appending one entry per
+    // internal node visited: the split feature, the split value, and whether
+    // the instance went left. The feature is the one carrying the
+    // largest-magnitude normal component, and the value is the intercept along
+    // that feature.
+    pub fn path_trace(&self, instance: &[f64], trace: &mut Vec<(usize, f64, bool)>) {
+        match self {
+            IsolationTreeNode::Terminal { .. } => {}
+            IsolationTreeNode::Internal {
+                normal,
+                intercept,
+                left,
+                right,
+                ..
+            } => {
+                let went_left = Self::hyperplane_dot(instance, normal, intercept) <= 0.0;
+
+                // Attribute the split to the dominant component of the normal.
+                let split_feature = normal
+                    .iter()
+                    .enumerate()
+                    .max_by(|(_, a), (_, b)| {
+                        a.abs()
+                            .partial_cmp(&b.abs())
+                            .unwrap_or(std::cmp::Ordering::Equal)
+                    })
+                    .map(|(feature, _)| feature)
+                    .unwrap_or(0);
+                trace.push((split_feature, intercept[split_feature], went_left));
+
+                if went_left {
+                    left.path_trace(instance, trace);
+                } else {
+                    right.path_trace(instance, trace);
+                }
+            }
+        }
+    }
+
+    // Evaluate dot(instance - intercept, normal) for the hyperplane split test
+    fn hyperplane_dot(instance: &[f64], normal: &[f64], intercept: &[f64]) -> f64 {
+        normal
+            .iter()
+            .zip(instance.iter())
+            .zip(intercept.iter())
+            .map(|((&n, &x), &p)| n * (x - p))
+            .sum()
+    }
+
     // Helper function to calculate average path length correction factor
     // This is the expected path length in a Binary Search Tree
     pub fn c(size: usize) -> f64 {
@@ -58,19 +118,27 @@ impl IsolationTreeNode {
         2.0 * (n.ln() + 0.5772156649) - (2.0 * (n - 1.0) / n)
     }
 
+    // Draw a single standard normal sample via the Box-Muller transform,
+    // keeping the crate's dependency surface limited to `rand`.
+    fn standard_normal(rng: &mut impl Rng) -> f64 {
+        let u1: f64 = rng.random_range(f64::EPSILON..=1.0);
+        let u2: f64 = rng.random_range(0.0..=1.0);
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+
     // Core of the algorithm
     pub fn build_isolation_tree(
-        data: &[Vec<f64>],
+        mut range: DatasetRange,
         depth: usize,
         height_limit: usize,
+        extension_level: usize,
         rng: &mut impl Rng,
     ) -> Self {
-        let sample_size = data.len();
+        let sample_size = range.len();
 
         // Terminal conditions:
         // 1. Only one instance remains (perfectly isolated)
-        // 2. All values in chosen attribute are identical (can't split further)
-        // 3. Maximum height (depth) has been reached
+        // 2. Maximum height (depth) has been reached
         if sample_size <= 1 || depth >= height_limit {
             return IsolationTreeNode::Terminal {
                 size: sample_size,
@@ -78,71 +146,94 @@ impl IsolationTreeNode {
             };
         }
 
-        // Get number of features
-        let num_features = if !data.is_empty() { data[0].len() } else { 0 };
-
-        // Randomly select a feature to split on
-        let split_feature = rng.random_range(0..num_features);
-
-        let mut min_val = f64::INFINITY;
-        let mut max_val = f64::NEG_INFINITY;
+        let num_features = range.num_features();
 
-        for instance in data.iter() {
-            let val = instance[split_feature];
-            min_val = min_val.min(val);
-            max_val = max_val.max(val);
+        // Per-feature min/max box of the current sample.
+        let mut min_vals = vec![f64::INFINITY; num_features];
+        let mut max_vals = vec![f64::NEG_INFINITY; num_features];
+        for row in 0..sample_size {
+            for feature in 0..num_features {
+                let val = range.at(row, feature);
+                min_vals[feature] = min_vals[feature].min(val);
+                max_vals[feature] = max_vals[feature].max(val);
+            }
         }
 
-        // Check if all values are identical
-        if (max_val - min_val).abs() < f64::EPSILON {
-            return IsolationTreeNode::Terminal {
-                size: sample_size,
-                depth,
-            };
+        // Draw a random normal vector, then keep only `extension_level + 1`
+        // randomly chosen components and zero out the rest. With
+        // `extension_level = 0` this leaves a single axis, i.e. the classic
+        // axis-parallel split.
+        let mut normal: Vec<f64> = (0..num_features)
+            .map(|_| Self::standard_normal(rng))
+            .collect();
+
+        let keep = min(extension_level + 1, num_features);
+        // Partial Fisher-Yates over feature indices to pick the kept subset.
+        let mut features: Vec<usize> = (0..num_features).collect();
+        for i in 0..keep {
+            let j = rng.random_range(i..num_features);
+            features.swap(i, j);
         }
-
-        // Generate a random split point between min and max
-        let split_value = rng.random_range(min_val..=max_val);
-
-        // Partition based on split
-        let mut left_data = Vec::new();
-        let mut right_data = Vec::new();
-
-        for instance in data.iter() {
-            if instance[split_feature] < split_value {
-                left_data.push(instance.clone());
-            } else {
-                right_data.push(instance.clone());
+        let kept: HashSet<usize> = features[..keep].iter().copied().collect();
+        for (feature, component) in normal.iter_mut().enumerate() {
+            if !kept.contains(&feature) {
+                *component = 0.0;
             }
         }
 
+        // Draw an intercept point uniformly within the per-feature box.
+        let intercept: Vec<f64> = (0..num_features)
+            .map(|feature| {
+                if (max_vals[feature] - min_vals[feature]).abs() < f64::EPSILON {
+                    min_vals[feature]
+                } else {
+                    rng.random_range(min_vals[feature]..=max_vals[feature])
+                }
+            })
+            .collect();
+
+        // Partition the index slice in place based on the hyperplane test.
+        let mask: Vec<bool> = (0..sample_size)
+            .map(|row| {
+                let dot: f64 = normal
+                    .iter()
+                    .enumerate()
+                    .map(|(feature, &n)| n * (range.at(row, feature) - intercept[feature]))
+                    .sum();
+                dot <= 0.0
+            })
+            .collect();
+        let mid = range.partition_mask(&mask);
+
         // If the split results in empty partitions, create a terminal node
-        if left_data.is_empty() || right_data.is_empty() {
+        if mid == 0 || mid == sample_size {
             return IsolationTreeNode::Terminal {
                 size: sample_size,
                 depth,
             };
         }
 
-        // Recursively build left and right subtrees
+        let (left_range, right_range) = range.split_at(mid);
+
         let left = Box::new(Self::build_isolation_tree(
-            &left_data,
+            left_range,
             depth + 1,
             height_limit,
+            extension_level,
             rng,
         ));
 
         let right = Box::new(Self::build_isolation_tree(
-            &right_data,
+            right_range,
             depth + 1,
             height_limit,
+            extension_level,
             rng,
         ));
 
-        // Create and return an internal node
         IsolationTreeNode::Internal {
-            split_feature,
-            split_value,
+            normal,
+            intercept,
             left,
             right,
             depth,
@@ -0,0 +1,27 @@
+use iforest::forest::IsolationForest;
+
+fn sample_data() -> Vec<Vec<f64>> {
+    vec![
+        vec![1.0, 2.0],
+        vec![1.1, 2.2],
+        vec![1.2, 2.1],
+        vec![1.3, 2.0],
+        vec![1.2, 2.3],
+        vec![10.0, 20.0],
+    ]
+}
+
+// A fixed seed must yield identical scores across independent runs, since the
+// subsampling draws without replacement deterministically under the seeded RNG.
+#[test]
+fn scores_are_deterministic_under_fixed_seed() {
+    let data = sample_data();
+
+    let mut first = IsolationForest::new(50, 4, 0, Some(7));
+    first.fit(&data);
+
+    let mut second = IsolationForest::new(50, 4, 0, Some(7));
+    second.fit(&data);
+
+    assert_eq!(first.score(&data), second.score(&data));
+}
@@ -0,0 +1,27 @@
+use iforest::forest::IsolationForest;
+
+fn sample_data() -> Vec<Vec<f64>> {
+    vec![
+        vec![1.0, 2.0],
+        vec![1.1, 2.2],
+        vec![1.2, 2.1],
+        vec![1.3, 2.0],
+        vec![1.2, 2.3],
+        vec![10.0, 20.0],
+    ]
+}
+
+// `explain` returns normalized per-feature contributions, so the weights for a
+// point that actually traverses internal nodes must sum to 1.
+#[test]
+fn explain_weights_sum_to_one() {
+    let data = sample_data();
+
+    let mut forest = IsolationForest::new(100, 4, 0, Some(42));
+    forest.fit(&data);
+
+    let contributions = forest.explain(&data[data.len() - 1]);
+    let total: f64 = contributions.iter().sum();
+
+    assert!((total - 1.0).abs() < 1e-9);
+}
@@ -0,0 +1,33 @@
+#![cfg(feature = "serde")]
+
+use iforest::forest::IsolationForest;
+
+fn sample_data() -> Vec<Vec<f64>> {
+    vec![
+        vec![1.0, 2.0],
+        vec![1.1, 2.2],
+        vec![1.2, 2.1],
+        vec![1.3, 2.0],
+        vec![1.2, 2.3],
+        vec![10.0, 20.0],
+    ]
+}
+
+// A forest saved and reloaded must score identically, since scoring only
+// depends on the persisted tree structure and not the RNG.
+#[test]
+fn save_load_roundtrip_reproduces_scores() {
+    let data = sample_data();
+
+    let mut forest = IsolationForest::new(100, 4, 0, Some(42));
+    forest.fit(&data);
+    let before = forest.score(&data);
+
+    let mut buffer = Vec::new();
+    forest.save_to_writer(&mut buffer).unwrap();
+
+    let loaded = IsolationForest::load_from_reader(&buffer[..]).unwrap();
+    let after = loaded.score(&data);
+
+    assert_eq!(before, after);
+}